@@ -1,15 +1,21 @@
+extern crate bstr;
 extern crate cid;
+extern crate flate2;
 extern crate hex;
 extern crate multihash;
+extern crate sha1;
 
+use bstr::BString;
 use cid::Cid;
 use std::collections::HashMap;
 use std::str;
 
 pub use node::Node;
-use util::{cleave_out_at_value, sha1_to_cid};
+use util::{cleave_out_at_value, cid_to_sha1, sha1_to_cid};
 
 mod node;
+pub mod packfile;
+pub mod store;
 pub mod util;
 
 pub struct Blob(Vec<u8>);
@@ -18,11 +24,19 @@ impl Node for Blob {
     fn links(&self) -> Vec<node::Link> {
         Vec::new()
     }
+
+    fn object_type(&self) -> &'static str {
+        "blob"
+    }
+
+    fn encode_body(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.0.clone())
+    }
 }
 
 pub struct Tree {
-    entries: HashMap<String, TreeEntry>,
-    order: Vec<String>,
+    entries: HashMap<BString, TreeEntry>,
+    order: Vec<BString>,
 }
 
 impl Tree {
@@ -43,24 +57,66 @@ impl Node for Tree {
                     .map(|(_, entry)| node::Link::new(&entry.cid))
                     .collect::<Vec<_>>()
     }
+
+    fn object_type(&self) -> &'static str {
+        "tree"
+    }
+
+    fn encode_body(&self) -> Result<Vec<u8>, Error> {
+        let mut buf = Vec::new();
+        for name in &self.order {
+            let entry = &self.entries[name];
+            buf.extend_from_slice(entry.mode.as_bytes());
+            buf.push(b' ');
+            buf.extend_from_slice(entry.name.as_slice());
+            buf.push(0);
+            buf.extend_from_slice(&cid_to_sha1(&entry.cid)?);
+        }
+        Ok(buf)
+    }
 }
 
 // TODO: change to use bytes crate for zero copy? constructing current struct
 //       requires copying and will be slow for large repos, I believe
-// TODO: file names can maybe have non-utf8 characters. figure out how git
-//       handles them?
 struct TreeEntry {
     pub mode: String,
-    pub name: String,
+    pub name: BString,
     pub cid: Cid,
 }
 
-// TODO: encoding, gpgsig, mergetag, non-standard headers?
 pub struct Commit {
     tree: Cid,
     parents: Vec<Cid>,
     author: UserInfo,
     committer: UserInfo,
+    // Every header field after `committer` (e.g. `encoding`, `gpgsig`,
+    // `mergetag`, or anything else git doesn't give special treatment to),
+    // in the order they appeared in. Git doesn't emit these in a fixed
+    // order (e.g. `encoding` comes right after `committer`, but `gpgsig` is
+    // always last), so an unmodified commit can only re-encode to the
+    // identical bytes if parse order is preserved rather than the fields
+    // being re-grouped by name.
+    extra_headers: Vec<(String, Vec<u8>)>,
+    message: Vec<u8>,
+}
+
+impl Commit {
+    fn extra_header(&self, name: &str) -> Option<&[u8]> {
+        self.extra_headers.iter()
+            .find(|&&(ref n, _)| n == name)
+            .map(|&(_, ref value)| value.as_slice())
+    }
+
+    // The commit's `gpgsig` header (its PGP/SSH signature), if present.
+    pub fn gpgsig(&self) -> Option<&[u8]> {
+        self.extra_header("gpgsig")
+    }
+
+    // The commit's `encoding` header (the character encoding of its
+    // message), if present.
+    pub fn encoding(&self) -> Option<&str> {
+        self.extra_header("encoding").and_then(|v| str::from_utf8(v).ok())
+    }
 }
 
 impl Node for Commit {
@@ -71,15 +127,101 @@ impl Node for Commit {
         v.extend(parent_links);
         v
     }
+
+    fn object_type(&self) -> &'static str {
+        "commit"
+    }
+
+    fn encode_body(&self) -> Result<Vec<u8>, Error> {
+        let mut buf = Vec::new();
+        encode_header_line(&mut buf, "tree", &self.tree)?;
+        for parent in &self.parents {
+            encode_header_line(&mut buf, "parent", parent)?;
+        }
+        encode_folded_header(&mut buf, "author", &encode_user_info(&self.author));
+        encode_folded_header(&mut buf, "committer", &encode_user_info(&self.committer));
+        for &(ref name, ref value) in &self.extra_headers {
+            encode_folded_header(&mut buf, name, value);
+        }
+        buf.push(b'\n');
+        buf.extend_from_slice(&self.message);
+        Ok(buf)
+    }
+}
+
+fn encode_header_line(buf: &mut Vec<u8>, name: &str, cid: &Cid) -> Result<(), Error> {
+    let digest = cid_to_sha1(cid)?;
+    buf.extend_from_slice(name.as_bytes());
+    buf.push(b' ');
+    buf.extend_from_slice(hex::encode(digest).as_bytes());
+    buf.push(b'\n');
+    Ok(())
+}
+
+// Encode a header field whose value may itself contain the embedded "\n "
+// continuation lines produced by `read_folded_value`, e.g. a `gpgsig` or
+// `mergetag` header.
+fn encode_folded_header(buf: &mut Vec<u8>, name: &str, value: &[u8]) {
+    buf.extend_from_slice(name.as_bytes());
+    buf.push(b' ');
+    buf.extend_from_slice(value);
+    buf.push(b'\n');
+}
+
+fn encode_user_info(info: &UserInfo) -> Vec<u8> {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(info.name.as_slice());
+    buf.extend_from_slice(b" <");
+    buf.extend_from_slice(info.email.as_slice());
+    buf.extend_from_slice(b"> ");
+    buf.extend_from_slice(info.timestamp.as_bytes());
+    buf.push(b' ');
+    buf.extend_from_slice(info.timezone.as_bytes());
+    buf
 }
 
 struct UserInfo {
-    pub name: String,
-    pub email: String,
+    pub name: BString,
+    pub email: BString,
     pub timestamp: String,
     pub timezone: String,
 }
 
+pub struct Tag {
+    object: Cid,
+    target_type: String,
+    name: String,
+    tagger: UserInfo,
+    message: Vec<u8>,
+}
+
+impl Node for Tag {
+    fn links(&self) -> Vec<node::Link> {
+        vec![node::Link::new(&self.object)]
+    }
+
+    fn object_type(&self) -> &'static str {
+        "tag"
+    }
+
+    fn encode_body(&self) -> Result<Vec<u8>, Error> {
+        let mut buf = Vec::new();
+        encode_header_line(&mut buf, "object", &self.object)?;
+        buf.extend_from_slice(b"type ");
+        buf.extend_from_slice(self.target_type.as_bytes());
+        buf.push(b'\n');
+        buf.extend_from_slice(b"tag ");
+        buf.extend_from_slice(self.name.as_bytes());
+        buf.push(b'\n');
+        buf.extend_from_slice(b"tagger ");
+        buf.extend_from_slice(&encode_user_info(&self.tagger));
+        buf.push(b'\n');
+        buf.push(b'\n');
+        buf.extend_from_slice(&self.message);
+        Ok(buf)
+    }
+}
+
 pub type Error = String;
 
 pub fn parse_object(buf: &[u8]) -> Result<Box<Node>, Error> {
@@ -88,10 +230,25 @@ pub fn parse_object(buf: &[u8]) -> Result<Box<Node>, Error> {
         ObjectType::Blob => Ok(Box::new(parse_blob_object(bytes)?)),
         ObjectType::Tree => Ok(Box::new(parse_tree_object(bytes)?)),
         ObjectType::Commit => Ok(Box::new(parse_commit_object(bytes)?)),
-        ObjectType::Tag => unimplemented!(),
+        ObjectType::Tag => Ok(Box::new(parse_tag_object(bytes)?)),
     }
 }
 
+// Serialize a node back to the canonical git wire format, i.e. the inverse
+// of `parse_object`: "<type> <size>\x00<body>".
+pub fn encode_object(node: &Node) -> Result<Vec<u8>, Error> {
+    let body = node.encode_body()?;
+
+    let mut buf = Vec::with_capacity(body.len() + 16);
+    buf.extend_from_slice(node.object_type().as_bytes());
+    buf.push(b' ');
+    buf.extend_from_slice(body.len().to_string().as_bytes());
+    buf.push(0);
+    buf.extend_from_slice(&body);
+
+    Ok(buf)
+}
+
 enum ObjectType {
     Blob,
     Tree,
@@ -182,11 +339,10 @@ fn parse_tree_entry(buf: &[u8]) -> Result<(Option<TreeEntry>, &[u8]), Error> {
                                       characters: {}", e)),
         Ok(s) => s.to_string(),
     };
-    let name = match str::from_utf8(name_bytes) {
-        Err(e) => return Err(format!("Tree entry name is invalid, contains non utf-8
-                                      characters: {}", e)),
-        Ok(s) => s.to_string(),
-    };
+    // Unlike the mode, git allows the entry name to be any byte sequence
+    // (e.g. file names in a legacy encoding), so it's kept as raw bytes
+    // rather than validated as UTF-8.
+    let name = BString::from(name_bytes.to_vec());
 
     let entry = Some(TreeEntry {
         mode: mode,
@@ -212,6 +368,9 @@ fn parse_tree_entry(buf: &[u8]) -> Result<(Option<TreeEntry>, &[u8]), Error> {
 //     author <author string>
 //     committer <committer string>
 //
+// A header field's value may continue onto following lines, each of which
+// begins with a single space (e.g. a `gpgsig` signature, or an embedded
+// `mergetag`); `read_folded_value` folds those in.
 fn parse_commit_object(mut bytes: &[u8]) -> Result<Commit, Error> {
     // parse the commit header, which is repeatedly parsing lines
     // until you see a blank line
@@ -219,6 +378,7 @@ fn parse_commit_object(mut bytes: &[u8]) -> Result<Commit, Error> {
     let mut parents: Vec<Cid> = Vec::new();
     let mut author_info: Option<UserInfo> = None;
     let mut committer_info: Option<UserInfo> = None;
+    let mut extra_headers: Vec<(String, Vec<u8>)> = Vec::new();
     loop {
         let (line, rest) = match cleave_out_at_value(bytes, b'\n') {
             None => return Err("Unexpected end of bytes".to_string()),
@@ -235,11 +395,13 @@ fn parse_commit_object(mut bytes: &[u8]) -> Result<Commit, Error> {
                                 form '<name> <data>'".to_string()),
             Some((l, r)) => (l, r),
         };
+        let (data, rest) = read_folded_value(data, bytes);
+        bytes = rest;
 
         match name {
             b"tree" => {
                 // TODO: convert data from 40-byte ASCII string to 20-byte bytestring
-                let digest = hex::decode(data)
+                let digest = hex::decode(&data)
                     .map_err(|_| "Tree hash is not valid hexadecimal")?;
                 if tree_cid.is_some() {
                     return Err("Invalid second tree entry found".to_string())
@@ -247,7 +409,7 @@ fn parse_commit_object(mut bytes: &[u8]) -> Result<Commit, Error> {
                 tree_cid = Some(sha1_to_cid(&digest)?);
             },
             b"parent" => {
-                let digest = hex::decode(data)
+                let digest = hex::decode(&data)
                     .map_err(|_| "Tree hash is not valid hexadecimal")?;
                 parents.push(sha1_to_cid(&digest)?);
             },
@@ -255,15 +417,31 @@ fn parse_commit_object(mut bytes: &[u8]) -> Result<Commit, Error> {
                 if author_info.is_some() {
                     return Err("Invalid second author entry found".to_string())
                 }
-                author_info = Some(parse_user_info(data)?)
+                author_info = Some(parse_user_info(&data)?)
             },
             b"committer" => {
                 if committer_info.is_some() {
                     return Err("Invalid second committer entry found".to_string())
                 }
-                committer_info = Some(parse_user_info(data)?)
+                committer_info = Some(parse_user_info(&data)?)
             },
-            _ => return Err(format!("Unrecognized commit header field name: {:?}", name)),
+            b"gpgsig" => {
+                if extra_headers.iter().any(|&(ref n, _)| n == "gpgsig") {
+                    return Err("Invalid second gpgsig entry found".to_string())
+                }
+                extra_headers.push(("gpgsig".to_string(), data));
+            },
+            b"encoding" => {
+                if extra_headers.iter().any(|&(ref n, _)| n == "encoding") {
+                    return Err("Invalid second encoding entry found".to_string())
+                }
+                // Validate it's UTF-8 up front, even though the raw bytes are
+                // what's kept (so they can be re-encoded byte-for-byte in
+                // the order they were parsed in).
+                byteslice_to_string(&data)?;
+                extra_headers.push(("encoding".to_string(), data));
+            },
+            _ => extra_headers.push((byteslice_to_string(name)?, data)),
         }
     }
 
@@ -286,9 +464,31 @@ fn parse_commit_object(mut bytes: &[u8]) -> Result<Commit, Error> {
         parents: parents,
         author: author_info.unwrap(),
         committer: committer_info.unwrap(),
+        extra_headers: extra_headers,
+        message: bytes.to_vec(),
     })
 }
 
+// Fold any continuation lines following a header field's first line into its
+// value: git allows a header value to continue onto following lines as long
+// as each one begins with a single space. The continuation lines' leading
+// spaces are kept as part of the returned value (joined by the `\n` that
+// separated them in `bytes`) so that the original bytes can be reconstructed
+// by writing `<name> ` followed by the value and a trailing `\n`.
+fn read_folded_value<'a>(data: &[u8], mut bytes: &'a [u8]) -> (Vec<u8>, &'a [u8]) {
+    let mut value = data.to_vec();
+    while bytes.first() == Some(&b' ') {
+        let (line, rest) = match cleave_out_at_value(bytes, b'\n') {
+            Some((l, r)) => (l, r),
+            None => break,
+        };
+        value.push(b'\n');
+        value.extend_from_slice(line);
+        bytes = rest;
+    }
+    (value, bytes)
+}
+
 fn parse_user_info(buf: &[u8]) -> Result<UserInfo, Error> {
     let (mut name, buf) = match cleave_out_at_value(buf, b'<') {
         None => return Err("User info is missing an email enclosed in angle \
@@ -314,8 +514,10 @@ fn parse_user_info(buf: &[u8]) -> Result<UserInfo, Error> {
     };
 
     Ok(UserInfo {
-        name: byteslice_to_string(name)?,
-        email: byteslice_to_string(email)?,
+        // Names and emails are kept as raw bytes since git allows authors
+        // and committers to use arbitrary (e.g. legacy) byte encodings here.
+        name: BString::from(name.to_vec()),
+        email: BString::from(email.to_vec()),
         timestamp: byteslice_to_string(timestamp)?,
         timezone: byteslice_to_string(timezone)?,
     })
@@ -327,6 +529,96 @@ fn byteslice_to_string(s: &[u8]) -> Result<String, Error> {
         .map_err(|e| format!("Error converting to utf-8 string: {}", e))
 }
 
+// Tag objects are structured like commits:
+//
+//     <tag header>
+//
+//     <tag message>
+//
+// where a blank line separates the header and message, and where the header
+// looks like, for example:
+//
+//     object <tagged object hash>
+//     type <blob|tree|commit|tag>
+//     tag <tag name>
+//     tagger <tagger string>
+//
+fn parse_tag_object(mut bytes: &[u8]) -> Result<Tag, Error> {
+    let mut object_cid: Option<Cid> = None;
+    let mut target_type: Option<String> = None;
+    let mut name: Option<String> = None;
+    let mut tagger_info: Option<UserInfo> = None;
+    loop {
+        let (line, rest) = match cleave_out_at_value(bytes, b'\n') {
+            None => return Err("Unexpected end of bytes".to_string()),
+            Some((l, r)) => (l, r),
+        };
+        bytes = rest;
+
+        if line.len() == 0 {
+            break;
+        }
+
+        let (field_name, data) = match cleave_out_at_value(line, b' ') {
+            None => return Err("Invalid tag header line, should be of the
+                                form '<name> <data>'".to_string()),
+            Some((l, r)) => (l, r),
+        };
+
+        match field_name {
+            b"object" => {
+                let digest = hex::decode(data)
+                    .map_err(|_| "Object hash is not valid hexadecimal")?;
+                if object_cid.is_some() {
+                    return Err("Invalid second object entry found".to_string())
+                }
+                object_cid = Some(sha1_to_cid(&digest)?);
+            },
+            b"type" => {
+                if target_type.is_some() {
+                    return Err("Invalid second type entry found".to_string())
+                }
+                target_type = Some(byteslice_to_string(data)?);
+            },
+            b"tag" => {
+                if name.is_some() {
+                    return Err("Invalid second tag entry found".to_string())
+                }
+                name = Some(byteslice_to_string(data)?);
+            },
+            b"tagger" => {
+                if tagger_info.is_some() {
+                    return Err("Invalid second tagger entry found".to_string())
+                }
+                tagger_info = Some(parse_user_info(data)?)
+            },
+            _ => return Err(format!("Unrecognized tag header field name: {:?}", field_name)),
+        }
+    }
+
+    fn missing_field_error(name: &str) -> String {
+        format!("Missing header field '{}'", name)
+    }
+
+    if object_cid.is_none() {
+        return Err(missing_field_error("object"))
+    } else if target_type.is_none() {
+        return Err(missing_field_error("type"))
+    } else if name.is_none() {
+        return Err(missing_field_error("tag"))
+    } else if tagger_info.is_none() {
+        return Err(missing_field_error("tagger"))
+    }
+
+    Ok(Tag {
+        object: object_cid.unwrap(),
+        target_type: target_type.unwrap(),
+        name: name.unwrap(),
+        tagger: tagger_info.unwrap(),
+        message: bytes.to_vec(),
+    })
+}
+
 #[cfg(test)]
 mod test {
     use hex;
@@ -368,4 +660,180 @@ mod test {
         assert_eq!(&commit.committer.timestamp, "1517914295");
         assert_eq!(&commit.committer.timezone, "+0100");
     }
+
+    #[test]
+    fn round_trip_commit() {
+        let mut full_object = format!("commit {}\0", INIT_COMMIT.len()).into_bytes();
+        full_object.extend_from_slice(INIT_COMMIT);
+
+        let node = super::parse_object(&full_object).unwrap();
+        let encoded = super::encode_object(&*node).unwrap();
+
+        assert_eq!(encoded, full_object);
+    }
+
+    // A signed commit with a folded `gpgsig`, an `encoding` header, and a
+    // `mergetag` header git doesn't give special treatment to.
+    const SIGNED_COMMIT: &'static [u8] = b"\
+        tree 7cee6dfa7d13e124220d2c04923f0cb0347ba27c\n\
+        parent abbee1260bdbb9cdb3d16680aeae7540839b9f42\n\
+        author Moloch <pure_machinery@example.com> 1517911033 -0600\n\
+        committer Jaden Doe <j.doe@example.com> 1517914295 +0100\n\
+        gpgsig -----BEGIN PGP SIGNATURE-----\n\
+        \x20iQIzBAABCAAdFiEE1234567890\n\
+        \x20abcdefghijklmnopqrstuvwxyz\n\
+        \x20-----END PGP SIGNATURE-----\n\
+        encoding ISO-8859-1\n\
+        mergetag object abbee1260bdbb9cdb3d16680aeae7540839b9f42\n\
+        \x20type commit\n\
+        \x20tag v0.9.0\n\
+        \n\
+        Merge commit with a signature.\n";
+
+    #[test]
+    fn parse_commit_with_extended_headers() {
+        let commit = match super::parse_commit_object(SIGNED_COMMIT) {
+            Err(e) => panic!("Parsing error: {}", e),
+            Ok(c) => c,
+        };
+
+        assert_eq!(commit.parents.len(), 1);
+
+        let gpgsig = commit.gpgsig().expect("gpgsig should have been parsed");
+        assert_eq!(gpgsig, &b"-----BEGIN PGP SIGNATURE-----\n iQIzBAABCAAdFiEE1234567890\n abcdefghijklmnopqrstuvwxyz\n -----END PGP SIGNATURE-----"[..]);
+
+        assert_eq!(commit.encoding(), Some("ISO-8859-1"));
+
+        assert_eq!(commit.extra_headers, vec![
+            ("gpgsig".to_string(),
+             b"-----BEGIN PGP SIGNATURE-----\n iQIzBAABCAAdFiEE1234567890\n abcdefghijklmnopqrstuvwxyz\n -----END PGP SIGNATURE-----".to_vec()),
+            ("encoding".to_string(), b"ISO-8859-1".to_vec()),
+            ("mergetag".to_string(),
+             b"object abbee1260bdbb9cdb3d16680aeae7540839b9f42\n type commit\n tag v0.9.0".to_vec()),
+        ]);
+    }
+
+    #[test]
+    fn round_trip_commit_with_extended_headers() {
+        let mut full_object = format!("commit {}\0", SIGNED_COMMIT.len()).into_bytes();
+        full_object.extend_from_slice(SIGNED_COMMIT);
+
+        let node = super::parse_object(&full_object).unwrap();
+        let encoded = super::encode_object(&*node).unwrap();
+
+        assert_eq!(encoded, full_object);
+    }
+
+    // A signed commit with headers in the order git actually emits them:
+    // `encoding` immediately follows `committer`, and `gpgsig` (along with
+    // any other extension header, like `mergetag`) comes last. This is the
+    // opposite order from `SIGNED_COMMIT` above, and is what catches an
+    // encoder that re-groups headers by name instead of preserving parse
+    // order.
+    const GIT_ORDERED_SIGNED_COMMIT: &'static [u8] = b"\
+        tree 7cee6dfa7d13e124220d2c04923f0cb0347ba27c\n\
+        parent abbee1260bdbb9cdb3d16680aeae7540839b9f42\n\
+        author Moloch <pure_machinery@example.com> 1517911033 -0600\n\
+        committer Jaden Doe <j.doe@example.com> 1517914295 +0100\n\
+        encoding ISO-8859-1\n\
+        mergetag object abbee1260bdbb9cdb3d16680aeae7540839b9f42\n\
+        \x20type commit\n\
+        \x20tag v0.9.0\n\
+        gpgsig -----BEGIN PGP SIGNATURE-----\n\
+        \x20iQIzBAABCAAdFiEE1234567890\n\
+        \x20abcdefghijklmnopqrstuvwxyz\n\
+        \x20-----END PGP SIGNATURE-----\n\
+        \n\
+        Merge commit with a signature.\n";
+
+    #[test]
+    fn round_trip_commit_with_git_ordered_extended_headers() {
+        let mut full_object = format!("commit {}\0", GIT_ORDERED_SIGNED_COMMIT.len()).into_bytes();
+        full_object.extend_from_slice(GIT_ORDERED_SIGNED_COMMIT);
+
+        let node = super::parse_object(&full_object).unwrap();
+        let encoded = super::encode_object(&*node).unwrap();
+
+        assert_eq!(encoded, full_object);
+    }
+
+    // An author name containing a raw, non-UTF-8 byte (e.g. "Jos\xe9" in
+    // Latin-1 rather than UTF-8's "Jos\xc3\xa9").
+    const NON_UTF8_AUTHOR_COMMIT: &'static [u8] = b"\
+        tree 7cee6dfa7d13e124220d2c04923f0cb0347ba27c\n\
+        author Jos\xe9 <author@example.com> 1517911033 -0600\n\
+        committer Jaden Doe <j.doe@example.com> 1517914295 +0100\n\
+        \n\
+        Initial commit.\n";
+
+    #[test]
+    fn round_trip_commit_with_non_utf8_author() {
+        let mut full_object = format!("commit {}\0", NON_UTF8_AUTHOR_COMMIT.len()).into_bytes();
+        full_object.extend_from_slice(NON_UTF8_AUTHOR_COMMIT);
+
+        let node = super::parse_object(&full_object).unwrap();
+        let encoded = super::encode_object(&*node).unwrap();
+
+        assert_eq!(encoded, full_object);
+    }
+
+    #[test]
+    fn round_trip_tree_with_non_utf8_name() {
+        let hash = hex::decode("abbee1260bdbb9cdb3d16680aeae7540839b9f42").unwrap();
+
+        let mut body = Vec::new();
+        body.extend_from_slice(b"100644");
+        body.push(b' ');
+        body.extend_from_slice(b"\xffweird-name.txt");
+        body.push(0);
+        body.extend_from_slice(&hash);
+
+        let mut full_object = format!("tree {}\0", body.len()).into_bytes();
+        full_object.extend_from_slice(&body);
+
+        let node = super::parse_object(&full_object).unwrap();
+        let encoded = super::encode_object(&*node).unwrap();
+
+        assert_eq!(encoded, full_object);
+    }
+
+    const INIT_TAG: &'static [u8] = b"\
+        object abbee1260bdbb9cdb3d16680aeae7540839b9f42\n\
+        type commit\n\
+        tag v1.0.0\n\
+        tagger Moloch <pure_machinery@example.com> 1517911033 -0600\n\
+        \n\
+        Initial release.\n";
+
+    #[test]
+    fn parse_tag() {
+        let tag = match super::parse_tag_object(INIT_TAG) {
+            Err(e) => panic!("Parsing error: {}", e),
+            Ok(t) => t,
+        };
+
+        let tag_object_multihash = multihash::decode(&tag.object.hash).unwrap();
+        let object_hash_hex = "abbee1260bdbb9cdb3d16680aeae7540839b9f42";
+        let object_hash = hex::decode(&object_hash_hex).unwrap();
+        assert_eq!(tag_object_multihash.digest, &object_hash[..]);
+
+        assert_eq!(&tag.target_type, "commit");
+        assert_eq!(&tag.name, "v1.0.0");
+
+        assert_eq!(&tag.tagger.name, "Moloch");
+        assert_eq!(&tag.tagger.email, "pure_machinery@example.com");
+        assert_eq!(&tag.tagger.timestamp, "1517911033");
+        assert_eq!(&tag.tagger.timezone, "-0600");
+    }
+
+    #[test]
+    fn round_trip_tag() {
+        let mut full_object = format!("tag {}\0", INIT_TAG.len()).into_bytes();
+        full_object.extend_from_slice(INIT_TAG);
+
+        let node = super::parse_object(&full_object).unwrap();
+        let encoded = super::encode_object(&*node).unwrap();
+
+        assert_eq!(encoded, full_object);
+    }
 }
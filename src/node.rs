@@ -1,8 +1,18 @@
 use cid::Cid;
 
+use Error;
+
 pub trait Node {
     // TODO: return an `impl Iterator<Item = Link<'a>>` instead?
     fn links<'a>(&'a self) -> Vec<Link<'a>>;
+
+    // The object type as git names it in the "<type> <size>\0" header, e.g.
+    // "blob", "tree", "commit" or "tag".
+    fn object_type(&self) -> &'static str;
+
+    // Serialize the object back to the bytes that follow the header, i.e.
+    // the inverse of whatever `parse_*_object` function produced this node.
+    fn encode_body(&self) -> Result<Vec<u8>, Error>;
 }
 
 #[derive(Debug)]
@@ -0,0 +1,376 @@
+use std::io::{Read, Write};
+
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+
+use cid::Cid;
+
+use node::Node;
+use util::{cleave_out_at_value, sha1_digest, sha1_to_cid};
+use {parse_object, Error};
+
+const PACK_MAGIC: &'static [u8; 4] = b"PACK";
+
+// The number of trailing bytes reserved for the pack's SHA-1 checksum.
+const CHECKSUM_LEN: usize = 20;
+
+enum EntryType {
+    Commit,
+    Tree,
+    Blob,
+    Tag,
+    OfsDelta,
+    RefDelta,
+}
+
+impl EntryType {
+    // The name used in the "<type> <size>\0" loose-object framing that
+    // `parse_object` expects.
+    fn object_type_name(&self) -> Result<&'static str, Error> {
+        match *self {
+            EntryType::Commit => Ok("commit"),
+            EntryType::Tree => Ok("tree"),
+            EntryType::Blob => Ok("blob"),
+            EntryType::Tag => Ok("tag"),
+            EntryType::OfsDelta =>
+                Err("ofs-delta packfile entries are not yet supported".to_string()),
+            EntryType::RefDelta =>
+                Err("ref-delta packfile entries are not yet supported".to_string()),
+        }
+    }
+
+    // The value packed into bits 4-6 of a packfile entry's type+size header.
+    fn type_bits(&self) -> u8 {
+        match *self {
+            EntryType::Commit => 1,
+            EntryType::Tree => 2,
+            EntryType::Blob => 3,
+            EntryType::Tag => 4,
+            EntryType::OfsDelta => 6,
+            EntryType::RefDelta => 7,
+        }
+    }
+
+    fn from_object_type_name(name: &[u8]) -> Result<EntryType, Error> {
+        match name {
+            b"commit" => Ok(EntryType::Commit),
+            b"tree" => Ok(EntryType::Tree),
+            b"blob" => Ok(EntryType::Blob),
+            b"tag" => Ok(EntryType::Tag),
+            _ => Err(format!("Invalid object type: expected one of \"blob\", \
+                              \"tree\", \"commit\" or \"tag\", got: {:?}", name)),
+        }
+    }
+}
+
+// Reads the entries of a version 2 git packfile, yielding fully-parsed,
+// CID-addressed `Node`s.
+//
+// A packfile is a 12-byte header (`"PACK"`, a 4-byte big-endian version,
+// and a 4-byte big-endian entry count), followed by that many variable-length
+// entries, followed by a trailing 20-byte SHA-1 checksum of everything that
+// came before it. This reader doesn't verify that trailing checksum; it
+// simply stops once it has read `entry_count` entries.
+pub struct PackReader<'a> {
+    remaining: &'a [u8],
+    entries_left: u32,
+}
+
+impl<'a> PackReader<'a> {
+    pub fn new(buf: &'a [u8]) -> Result<PackReader<'a>, Error> {
+        if buf.len() < 12 + CHECKSUM_LEN {
+            return Err("Packfile is too short to contain a valid header and \
+                        checksum".to_string());
+        }
+
+        if &buf[0..4] != PACK_MAGIC {
+            return Err(format!("Invalid packfile magic: expected {:?}, got {:?}",
+                               PACK_MAGIC, &buf[0..4]));
+        }
+
+        let version = read_be_u32(&buf[4..8]);
+        if version != 2 {
+            return Err(format!("Unsupported packfile version: expected 2, got {}",
+                               version));
+        }
+
+        let entry_count = read_be_u32(&buf[8..12]);
+
+        Ok(PackReader { remaining: &buf[12..], entries_left: entry_count })
+    }
+}
+
+impl<'a> Iterator for PackReader<'a> {
+    type Item = Result<(Cid, Box<Node>), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.entries_left == 0 {
+            return None;
+        }
+        self.entries_left -= 1;
+
+        let (entry_type, size, header_len) = match parse_entry_header(self.remaining) {
+            Ok(v) => v,
+            Err(e) => return Some(Err(e)),
+        };
+        let type_name = match entry_type.object_type_name() {
+            Ok(n) => n,
+            Err(e) => return Some(Err(e)),
+        };
+        self.remaining = &self.remaining[header_len..];
+
+        let (body, consumed) = match inflate(self.remaining, size) {
+            Ok(v) => v,
+            Err(e) => return Some(Err(e)),
+        };
+        self.remaining = &self.remaining[consumed..];
+
+        let mut full_object = format!("{} {}\0", type_name, body.len()).into_bytes();
+        full_object.extend_from_slice(&body);
+
+        let node = match parse_object(&full_object) {
+            Ok(n) => n,
+            Err(e) => return Some(Err(e)),
+        };
+
+        let cid = match sha1_to_cid(&sha1_digest(&full_object)) {
+            Ok(c) => c,
+            Err(e) => return Some(Err(e)),
+        };
+
+        Some(Ok((cid, node)))
+    }
+}
+
+// Builds a version 2 git packfile out of already-encoded git objects, i.e.
+// objects already in the "<type> <size>\0<body>" framing produced by
+// `encode_object`.
+pub struct PackFile {
+    objects: Vec<Vec<u8>>,
+}
+
+impl PackFile {
+    pub fn new() -> PackFile {
+        PackFile { objects: Vec::new() }
+    }
+
+    pub fn add_object(&mut self, encoded_object: Vec<u8>) {
+        self.objects.push(encoded_object);
+    }
+
+    // Serialize the accumulated objects into a pack: the `"PACK"` magic,
+    // version `2`, and the entry count, followed by a compressed entry per
+    // object, followed by a 20-byte SHA-1 checksum of everything written
+    // so far.
+    pub fn encode(&self) -> Result<Vec<u8>, Error> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(PACK_MAGIC);
+        write_be_u32(&mut buf, 2);
+        write_be_u32(&mut buf, self.objects.len() as u32);
+
+        for object in &self.objects {
+            let (entry_type, body) = parse_encoded_object_header(object)?;
+            write_entry_header(&mut buf, &entry_type, body.len() as u64);
+            buf.extend_from_slice(&deflate(body)?);
+        }
+
+        let checksum = sha1_digest(&buf);
+        buf.extend_from_slice(&checksum);
+
+        Ok(buf)
+    }
+}
+
+// Split an already-encoded object's "<type> <size>\0<body>" framing into its
+// entry type and body, without re-validating the size (it's trusted to have
+// come from `encode_object`).
+fn parse_encoded_object_header(buf: &[u8]) -> Result<(EntryType, &[u8]), Error> {
+    let (header, body) = cleave_out_at_value(buf, 0)
+        .ok_or_else(|| "Invalid format for git object, missing null byte".to_string())?;
+    let (type_name, _size) = cleave_out_at_value(header, b' ')
+        .ok_or_else(|| "Invalid format for git object header, must be \
+                        '<type> <size>'".to_string())?;
+
+    Ok((EntryType::from_object_type_name(type_name)?, body))
+}
+
+// The inverse of `parse_entry_header`: pack `size` into bits 0-3 plus as
+// many 7-bit continuation bytes as needed, with `entry_type` in bits 4-6 of
+// the first byte.
+fn write_entry_header(buf: &mut Vec<u8>, entry_type: &EntryType, size: u64) {
+    let mut first = (entry_type.type_bits() << 4) | ((size & 0x0f) as u8);
+    let mut size = size >> 4;
+    if size > 0 {
+        first |= 0x80;
+    }
+    buf.push(first);
+
+    while size > 0 {
+        let mut byte = (size & 0x7f) as u8;
+        size >>= 7;
+        if size > 0 {
+            byte |= 0x80;
+        }
+        buf.push(byte);
+    }
+}
+
+fn deflate(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)
+        .map_err(|e| format!("Error deflating packfile entry: {}", e))?;
+    encoder.finish()
+        .map_err(|e| format!("Error finishing packfile entry compression: {}", e))
+}
+
+fn write_be_u32(buf: &mut Vec<u8>, n: u32) {
+    buf.push((n >> 24) as u8);
+    buf.push((n >> 16) as u8);
+    buf.push((n >> 8) as u8);
+    buf.push(n as u8);
+}
+
+fn read_be_u32(buf: &[u8]) -> u32 {
+    ((buf[0] as u32) << 24) | ((buf[1] as u32) << 16) |
+    ((buf[2] as u32) << 8) | (buf[3] as u32)
+}
+
+// Parse a packfile entry's variable-length type+size header, returning the
+// entry's type, its inflated size, and the number of bytes the header
+// occupied.
+//
+// The first byte's MSB is a continuation flag, bits 4-6 encode the type,
+// and the low 4 bits are the least-significant bits of the size. Each
+// continuation byte contributes another 7 bits (little-endian) to the size,
+// again with its MSB as a continuation flag.
+fn parse_entry_header(buf: &[u8]) -> Result<(EntryType, u64, usize), Error> {
+    if buf.is_empty() {
+        return Err("Unexpected end of packfile while reading an entry header"
+                   .to_string());
+    }
+
+    let first = buf[0];
+    let entry_type = match (first >> 4) & 0x7 {
+        1 => EntryType::Commit,
+        2 => EntryType::Tree,
+        3 => EntryType::Blob,
+        4 => EntryType::Tag,
+        6 => EntryType::OfsDelta,
+        7 => EntryType::RefDelta,
+        n => return Err(format!("Unknown packfile entry type: {}", n)),
+    };
+
+    let mut size = (first & 0x0f) as u64;
+    let mut shift = 4;
+    let mut consumed = 1;
+    let mut has_more = first & 0x80 != 0;
+
+    while has_more {
+        if consumed >= buf.len() {
+            return Err("Unexpected end of packfile while reading an entry \
+                        size".to_string());
+        }
+        let byte = buf[consumed];
+        size |= ((byte & 0x7f) as u64) << shift;
+        shift += 7;
+        has_more = byte & 0x80 != 0;
+        consumed += 1;
+    }
+
+    Ok((entry_type, size, consumed))
+}
+
+// Zlib-inflate a single packfile entry's body out of `data`, returning the
+// inflated bytes and the number of (compressed) bytes of `data` consumed.
+fn inflate(data: &[u8], expected_size: u64) -> Result<(Vec<u8>, usize), Error> {
+    let mut decoder = ZlibDecoder::new(data);
+    let mut body = Vec::with_capacity(expected_size as usize);
+
+    decoder.read_to_end(&mut body)
+        .map_err(|e| format!("Error inflating packfile entry: {}", e))?;
+
+    if body.len() as u64 != expected_size {
+        return Err(format!("Packfile entry size mismatch: header specified \
+                            {} bytes, inflated to {} bytes",
+                            expected_size, body.len()));
+    }
+
+    Ok((body, decoder.total_in() as usize))
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use flate2::Compression;
+    use flate2::write::ZlibEncoder;
+
+    use {encode_object, parse_object};
+
+    use super::{PackFile, PackReader};
+
+    // Build a minimal (checksum not computed) v2 packfile containing a
+    // single blob entry, for exercising `PackReader`.
+    fn build_single_blob_pack(blob: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(blob).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let mut pack = Vec::new();
+        pack.extend_from_slice(b"PACK");
+        pack.extend_from_slice(&[0, 0, 0, 2]); // version
+        pack.extend_from_slice(&[0, 0, 0, 1]); // entry count
+
+        // Entry header: type 3 (blob) in bits 4-6, size in the low 4 bits
+        // (no continuation needed for sizes under 16).
+        assert!(blob.len() < 16);
+        pack.push(0b0011_0000 | (blob.len() as u8));
+        pack.extend_from_slice(&compressed);
+
+        // 20-byte trailer; `PackReader` doesn't verify it.
+        pack.extend_from_slice(&[0u8; 20]);
+
+        pack
+    }
+
+    #[test]
+    fn read_single_blob_pack() {
+        let blob_contents = b"hello world";
+        let pack = build_single_blob_pack(blob_contents);
+
+        let mut entries = PackReader::new(&pack).unwrap();
+        let (_cid, node) = entries.next().unwrap().unwrap();
+
+        assert_eq!(node.object_type(), "blob");
+        assert_eq!(node.encode_body().unwrap(), blob_contents);
+
+        assert!(entries.next().is_none());
+    }
+
+    #[test]
+    fn rejects_bad_magic() {
+        let mut pack = vec![0u8; 32];
+        pack[0..4].copy_from_slice(b"NOPE");
+
+        assert!(PackReader::new(&pack).is_err());
+    }
+
+    #[test]
+    fn write_and_read_round_trip() {
+        let blob_node = parse_object(b"blob 11\0hello world").unwrap();
+        let encoded_blob = encode_object(&*blob_node).unwrap();
+
+        let mut pack_file = PackFile::new();
+        pack_file.add_object(encoded_blob.clone());
+        let pack = pack_file.encode().unwrap();
+
+        let mut entries = PackReader::new(&pack).unwrap();
+        let (_cid, node) = entries.next().unwrap().unwrap();
+
+        assert_eq!(node.object_type(), "blob");
+        assert_eq!(encode_object(&*node).unwrap(), encoded_blob);
+
+        assert!(entries.next().is_none());
+    }
+}
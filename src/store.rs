@@ -0,0 +1,165 @@
+use std::collections::{HashMap, HashSet};
+
+use cid::Cid;
+
+use node::Node;
+use parse_object;
+use Error;
+
+// A minimal content-addressed block store: git objects keyed by the `Cid`
+// of their loose-object bytes.
+pub trait BlockStore {
+    fn get(&self, cid: &Cid) -> Option<&[u8]>;
+    fn put(&mut self, cid: Cid, block: Vec<u8>);
+}
+
+// A simple in-memory `BlockStore`, e.g. for tests or for staging a pack
+// before it's written out.
+pub struct MemoryBlockStore {
+    blocks: HashMap<Cid, Vec<u8>>,
+}
+
+impl MemoryBlockStore {
+    pub fn new() -> MemoryBlockStore {
+        MemoryBlockStore { blocks: HashMap::new() }
+    }
+}
+
+impl BlockStore for MemoryBlockStore {
+    fn get(&self, cid: &Cid) -> Option<&[u8]> {
+        self.blocks.get(cid).map(|block| block.as_slice())
+    }
+
+    fn put(&mut self, cid: Cid, block: Vec<u8>) {
+        self.blocks.insert(cid, block);
+    }
+}
+
+// Walks the DAG reachable from a root `Cid` through a `BlockStore`,
+// following `Node::links` transitively.
+pub struct Resolver<'a, S: 'a + BlockStore> {
+    store: &'a S,
+}
+
+impl<'a, S: BlockStore> Resolver<'a, S> {
+    pub fn new(store: &'a S) -> Resolver<'a, S> {
+        Resolver { store: store }
+    }
+
+    // Parse the block at `root` and every block transitively reachable
+    // from it through `Node::links`, returning each `(Cid, Box<Node>)`
+    // exactly once. The same tree/blob can be linked from many commits or
+    // parent trees, so already-visited CIDs are skipped via a `visited`
+    // set rather than re-parsed.
+    pub fn resolve(&self, root: &Cid) -> Result<Vec<(Cid, Box<Node>)>, Error> {
+        let mut visited = HashSet::new();
+        let mut reachable = Vec::new();
+        self.resolve_into(root, &mut visited, &mut reachable)?;
+        Ok(reachable)
+    }
+
+    fn resolve_into(&self, cid: &Cid, visited: &mut HashSet<Cid>,
+                     reachable: &mut Vec<(Cid, Box<Node>)>) -> Result<(), Error> {
+        if visited.contains(cid) {
+            return Ok(());
+        }
+        visited.insert(cid.clone());
+
+        let block = self.store.get(cid)
+            .ok_or_else(|| format!("No block found in store for Cid: {:?}", cid))?;
+        let node = parse_object(block)?;
+
+        let child_cids = node.links().into_iter()
+                              .map(|link| link.cid.clone())
+                              .collect::<Vec<_>>();
+
+        reachable.push((cid.clone(), node));
+
+        for child_cid in &child_cids {
+            self.resolve_into(child_cid, visited, reachable)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cid::Cid;
+    use hex;
+    use util::{cid_to_sha1, sha1_digest, sha1_to_cid};
+
+    use super::{BlockStore, MemoryBlockStore, Resolver};
+
+    fn put_loose_object(store: &mut MemoryBlockStore, object_type: &str, body: &[u8]) -> Cid {
+        let mut full_object = format!("{} {}\0", object_type, body.len()).into_bytes();
+        full_object.extend_from_slice(body);
+
+        let cid = sha1_to_cid(&sha1_digest(&full_object)).unwrap();
+        store.put(cid.clone(), full_object);
+        cid
+    }
+
+    #[test]
+    fn resolve_commit_dag() {
+        let mut store = MemoryBlockStore::new();
+
+        let blob_cid = put_loose_object(&mut store, "blob", b"hello world");
+
+        let mut tree_body = Vec::new();
+        tree_body.extend_from_slice(b"100644 file.txt\0");
+        tree_body.extend_from_slice(&cid_to_sha1(&blob_cid).unwrap());
+        let tree_cid = put_loose_object(&mut store, "tree", &tree_body);
+
+        let commit_body = format!(
+            "tree {}\n\
+             author A U Thor <author@example.com> 1517911033 -0600\n\
+             committer A U Thor <author@example.com> 1517911033 -0600\n\
+             \n\
+             Initial commit.\n",
+            hex_cid(&tree_cid));
+        let commit_cid = put_loose_object(&mut store, "commit", commit_body.as_bytes());
+
+        let resolver = Resolver::new(&store);
+        let reachable = resolver.resolve(&commit_cid).unwrap();
+
+        assert_eq!(reachable.len(), 3);
+        let cids = reachable.iter().map(|&(ref cid, _)| cid.clone()).collect::<Vec<_>>();
+        assert!(cids.contains(&commit_cid));
+        assert!(cids.contains(&tree_cid));
+        assert!(cids.contains(&blob_cid));
+    }
+
+    #[test]
+    fn resolve_deduplicates_shared_blocks() {
+        let mut store = MemoryBlockStore::new();
+
+        let blob_cid = put_loose_object(&mut store, "blob", b"shared contents");
+
+        let mut tree_body = Vec::new();
+        tree_body.extend_from_slice(b"100644 a.txt\0");
+        tree_body.extend_from_slice(&cid_to_sha1(&blob_cid).unwrap());
+        tree_body.extend_from_slice(b"100644 b.txt\0");
+        tree_body.extend_from_slice(&cid_to_sha1(&blob_cid).unwrap());
+        let tree_cid = put_loose_object(&mut store, "tree", &tree_body);
+
+        let resolver = Resolver::new(&store);
+        let reachable = resolver.resolve(&tree_cid).unwrap();
+
+        // The blob is linked twice from the tree, but should only appear once.
+        assert_eq!(reachable.len(), 2);
+    }
+
+    #[test]
+    fn resolve_missing_block_is_an_error() {
+        let store = MemoryBlockStore::new();
+        let blob_cid = sha1_to_cid(&sha1_digest(b"blob 5\0hello")).unwrap();
+
+        let resolver = Resolver::new(&store);
+        assert!(resolver.resolve(&blob_cid).is_err());
+    }
+
+    fn hex_cid(cid: &Cid) -> String {
+        hex::encode(&cid_to_sha1(cid).unwrap())
+    }
+}
@@ -1,5 +1,6 @@
 use cid::{self, Cid};
 use multihash;
+use sha1::Sha1;
 
 use Error;
 
@@ -36,6 +37,23 @@ pub fn sha1_to_cid(digest: &[u8]) -> Result<Cid, Error> {
     Ok(Cid::new(cid::Codec::GitRaw, cid::Version::V1, &mh))
 }
 
+// The inverse of `sha1_to_cid`: pull the raw 20-byte SHA-1 digest back out
+// of a `Cid`'s multihash, e.g. for re-encoding a git object.
+pub fn cid_to_sha1(cid: &Cid) -> Result<Vec<u8>, Error> {
+    let mh = multihash::decode(&cid.hash)
+        .map_err(|e| format!("Cannot decode Cid's multihash: {:?}", e))?;
+    Ok(mh.digest.to_vec())
+}
+
+// Compute the raw 20-byte SHA-1 digest of `bytes`, e.g. for deriving a
+// packfile entry's `Cid` from its "<type> <size>\0<body>" framing, or for
+// the trailing checksum of a packfile itself.
+pub fn sha1_digest(bytes: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha1::new();
+    hasher.update(bytes);
+    hasher.digest().bytes().to_vec()
+}
+
 #[cfg(test)]
 mod test {
     use sha1_to_cid;
@@ -59,4 +77,30 @@ mod test {
         assert_eq!(mh.alg, multihash::Hash::SHA1);
         assert_eq!(mh.digest, &test_digest_bytes[..]);
     }
+
+    #[test]
+    fn test_cid_to_sha1_round_trip() {
+        use hex;
+        use cid_to_sha1;
+
+        let test_sha1 = "a94a8fe5ccb19ba61c4c0873d391e987982fbbd3";
+        let test_digest_bytes = hex::decode(test_sha1).unwrap();
+
+        let cid = sha1_to_cid(&test_digest_bytes).unwrap();
+        let digest = cid_to_sha1(&cid).unwrap();
+
+        assert_eq!(digest, test_digest_bytes);
+    }
+
+    #[test]
+    fn test_sha1_digest() {
+        use hex;
+        use super::sha1_digest;
+
+        // SHA-1 of "test" (`echo -n "test" | sha1sum`)
+        let test_sha1 = "a94a8fe5ccb19ba61c4c0873d391e987982fbbd3";
+        let test_digest_bytes = hex::decode(test_sha1).unwrap();
+
+        assert_eq!(sha1_digest(b"test"), test_digest_bytes);
+    }
 }